@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::fs::File;
 use std::pin::Pin;
@@ -63,6 +64,10 @@ pub struct Bucket {
 	pub(crate) meta: BucketMeta,
 	pub(crate) root: PageNodeID,
 	dirty: bool,
+	// cache for `len()`/`is_empty()` on a bucket whose on-disk meta predates `BucketMeta::count`;
+	// not persisted; filled by a one-time cursor walk, seeded with `Some(0)` for a new
+	// child bucket, and refreshed by `incr_count` after every insert or delete
+	count_cache: Cell<Option<u64>>,
 	buckets: HashMap<Vec<u8>, Pin<Box<Bucket>>>,
 	nodes: Vec<Pin<Box<Node>>>,
 	page_node_ids: HashMap<PageID, NodeID>,
@@ -77,6 +82,7 @@ impl Bucket {
 			meta,
 			root: PageNodeID::Page(meta.root_page),
 			dirty: false,
+			count_cache: Cell::new(None),
 			buckets: HashMap::new(),
 			nodes: Vec::new(),
 			page_node_ids: HashMap::new(),
@@ -87,9 +93,10 @@ impl Bucket {
 	fn new_child(&mut self, name: &[u8]) {
 		let b = Bucket {
 			tx: Ptr::new(&self.tx),
-			meta: BucketMeta::default(),
+			meta: BucketMeta::new(),
 			root: PageNodeID::Node(0),
 			dirty: true,
+			count_cache: Cell::new(Some(0)),
 			buckets: HashMap::new(),
 			nodes: Vec::new(),
 			page_node_ids: HashMap::new(),
@@ -116,6 +123,7 @@ impl Bucket {
 			meta,
 			root: PageNodeID::Page(meta.root_page),
 			dirty: false,
+			count_cache: Cell::new(None),
 			buckets: HashMap::new(),
 			nodes: Vec::new(),
 			page_node_ids: HashMap::new(),
@@ -160,22 +168,27 @@ impl Bucket {
 			if !exists {
 				return Err(Error::BucketMissing);
 			}
-			match c.current() {
-				Some(data) => match data {
-					Data::Bucket(data) => {
-						let mut b = self.from_meta(data.meta());
-						b.meta = data.meta();
-						b.dirty = false;
-						self.buckets.insert(key.clone(), Pin::new(Box::new(b)));
-					}
-					_ => return Err(Error::IncompatibleValue),
-				},
-				None => return Err(Error::BucketMissing),
-			}
+			let data = c.current().ok_or(Error::BucketMissing)?;
+			self.cache_bucket(&key, data)?;
 		}
 		Ok(self.buckets.get_mut(&key).unwrap())
 	}
 
+	// Materializes the nested bucket a cursor landed on into `self.buckets[key]`.
+	// Shared by `get_bucket` and `get_or_create_bucket` so both agree on what counts
+	// as a hit without either one having to re-seek to find out.
+	fn cache_bucket(&mut self, key: &[u8], data: Data) -> Result<()> {
+		match data {
+			Data::Bucket(data) => {
+				let mut b = self.from_meta(data.meta());
+				b.dirty = false;
+				self.buckets.insert(Vec::from(key), Pin::new(Box::new(b)));
+				Ok(())
+			}
+			_ => Err(Error::IncompatibleValue),
+		}
+	}
+
 	/// Creates a new bucket.
 	///
 	/// Returns an error if the given key already exists.
@@ -217,6 +230,7 @@ impl Bucket {
 			return Err(Error::BucketExists);
 		}
 		self.meta.next_int += 1;
+		self.incr_count(1);
 		let key = Vec::from(name);
 		self.new_child(&key);
 
@@ -233,6 +247,184 @@ impl Bucket {
 		Ok(b)
 	}
 
+	/// Returns the existing nested bucket for the given key, creating it first if it
+	/// doesn't exist yet.
+	///
+	/// Returns an error if
+	/// 1. the transaction is read-only, or
+	/// 2. the key already exists but is key / value data, not a bucket.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jammdb::{DB};
+	/// # use jammdb::Error;
+	///
+	/// # fn main() -> Result<(), Error> {
+	/// let mut db = DB::open("my.db")?;
+	/// let mut tx = db.tx(true)?;
+	///
+	/// let bucket = tx.create_bucket("my-bucket")?;
+	///
+	/// // creates the nested bucket since it doesn't exist yet
+	/// bucket.get_or_create_bucket("nested-bucket")?;
+	///
+	/// // the second call just returns the bucket created above
+	/// bucket.get_or_create_bucket("nested-bucket")?;
+	///
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn get_or_create_bucket<T: AsRef<[u8]>>(&mut self, name: T) -> Result<&mut Bucket> {
+		if !self.tx.writable {
+			return Err(Error::ReadOnlyTx);
+		}
+		let name = name.as_ref();
+		let key = Vec::from(name);
+		if self.buckets.contains_key(&key) {
+			return Ok(self.buckets.get_mut(&key).unwrap());
+		}
+		// A single seek, reused for both the hit and the miss case, so callers avoid
+		// the get-then-put double traversal of calling get_bucket then create_bucket.
+		let mut c = self.cursor();
+		let exists = c.seek(name);
+		if exists {
+			self.cache_bucket(&key, c.current().unwrap())?;
+			return Ok(self.buckets.get_mut(&key).unwrap());
+		}
+		self.dirty = true;
+		self.meta.next_int += 1;
+		self.incr_count(1);
+		self.new_child(&key);
+
+		let data;
+		{
+			let b = self.buckets.get(&key).unwrap();
+			let name = self.tx.copy_data(name);
+			data = Data::Bucket(BucketData::from_meta(name, &b.meta));
+		}
+
+		let node = self.node(c.current_id());
+		node.insert_data(data);
+		Ok(self.buckets.get_mut(&key).unwrap())
+	}
+
+	/// Deletes an existing bucket and everything inside of it, including nested buckets.
+	///
+	/// Returns an error if
+	/// 1. the transaction is read-only, or
+	/// 2. the given key does not exist, or
+	/// 3. the key is for key / value data, not a bucket.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jammdb::{DB};
+	/// # use jammdb::Error;
+	///
+	/// # fn main() -> Result<(), Error> {
+	/// let mut db = DB::open("my.db")?;
+	/// let mut tx = db.tx(true)?;
+	///
+	/// // create a root-level bucket
+	/// let bucket = tx.create_bucket("my-bucket")?;
+	///
+	/// // create nested bucket
+	/// bucket.create_bucket("nested-bucket")?;
+	///
+	/// // delete the nested bucket, and everything inside of it
+	/// bucket.delete_bucket("nested-bucket")?;
+	///
+	/// assert!(bucket.get_bucket("nested-bucket").is_err());
+	///
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn delete_bucket<T: AsRef<[u8]>>(&mut self, name: T) -> Result<()> {
+		if !self.tx.writable {
+			return Err(Error::ReadOnlyTx);
+		}
+		let name = name.as_ref();
+		let mut c = self.cursor();
+		let exists = c.seek(name);
+		if !exists {
+			return Err(Error::BucketMissing);
+		}
+		match c.current() {
+			Some(data) => {
+				if data.is_kv() {
+					return Err(Error::IncompatibleValue);
+				}
+			}
+			None => return Err(Error::BucketMissing),
+		}
+		self.dirty = true;
+		self.incr_count(-1);
+		// load the bucket (and pull in its already-opened children) so we can
+		// walk its whole subtree and free every page it owns
+		self.get_bucket(name)?;
+		let key = Vec::from(name);
+		if let Some(mut b) = self.buckets.remove(&key) {
+			b.free_all_pages();
+		}
+		let node = self.node(c.current_id());
+		node.delete(c.current_index());
+		Ok(())
+	}
+
+	/// Frees every page owned by this bucket, recursing into nested buckets first.
+	/// Called right before a bucket is removed from its parent via [`delete_bucket`](#method.delete_bucket).
+	fn free_all_pages(&mut self) {
+		let cached: Vec<Vec<u8>> = self.buckets.keys().cloned().collect();
+		for key in &cached {
+			if let Some(b) = self.buckets.get_mut(key) {
+				b.free_all_pages();
+			}
+		}
+		self.free_subtree(self.root, &cached);
+	}
+
+	fn free_subtree(&mut self, id: PageNodeID, skip: &[Vec<u8>]) {
+		let (page_id, branch_pages, bucket_entries) = {
+			let node = self.node(id);
+			let page_id = node.page_id;
+			match &node.data {
+				NodeData::Branches(branches) => (
+					page_id,
+					branches.iter().map(|b| b.page).collect::<Vec<_>>(),
+					Vec::new(),
+				),
+				NodeData::Leaves(leaves) => (
+					page_id,
+					Vec::new(),
+					leaves
+						.iter()
+						.filter_map(|d| match d {
+							Data::Bucket(bd) => Some((bd.name().to_vec(), bd.meta())),
+							_ => None,
+						})
+						.collect::<Vec<_>>(),
+				),
+			}
+		};
+		for page in branch_pages {
+			// `node()` asserts a page's parent is known before it will materialize it,
+			// same as `rightmost_leaf`; without this, freeing anything but a single-page
+			// bucket panics as soon as recursion reaches a child branch page.
+			self.add_page_parent(page, page_id);
+			self.free_subtree(PageNodeID::Page(page), skip);
+		}
+		for (name, meta) in bucket_entries {
+			if !skip.contains(&name) {
+				let mut b = self.from_meta(meta);
+				b.free_all_pages();
+			}
+		}
+		let node = self.node(id);
+		node.free_page();
+		node.deleted = true;
+	}
+
 	/// Returns the next integer for the bucket.
 	/// The integer is automatically incremented each time a new key is added to the bucket.
 	/// You can it as a unique key for the bucket, since it will increment each time you add something new.
@@ -272,6 +464,86 @@ impl Bucket {
 		self.meta.next_int
 	}
 
+	/// Returns the number of entries (key / value pairs and nested buckets)
+	/// directly inside this bucket.
+	///
+	/// This is backed by a counter that is kept up to date as entries are
+	/// added or removed, so it's O(1) rather than walking a [`Cursor`] over
+	/// the whole bucket. A bucket opened from a database written before this
+	/// counter existed recomputes it once, the first time it is needed: by
+	/// `len()`, [`is_empty()`](#method.is_empty), or the first insert or delete.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jammdb::{DB};
+	/// # use jammdb::Error;
+	///
+	/// # fn main() -> Result<(), Error> {
+	/// let mut db = DB::open("my.db")?;
+	/// let mut tx = db.tx(true)?;
+	///
+	/// let bucket = tx.create_bucket("my-bucket")?;
+	/// assert_eq!(bucket.len(), 0);
+	///
+	/// bucket.put("key", "value")?;
+	/// assert_eq!(bucket.len(), 1);
+	///
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn len(&self) -> u64 {
+		self.ensure_count()
+	}
+
+	/// Returns `true` if this bucket has no entries.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jammdb::{DB};
+	/// # use jammdb::Error;
+	///
+	/// # fn main() -> Result<(), Error> {
+	/// let mut db = DB::open("my.db")?;
+	/// let mut tx = db.tx(true)?;
+	///
+	/// let bucket = tx.create_bucket("my-bucket")?;
+	/// assert!(bucket.is_empty());
+	///
+	/// bucket.put("key", "value")?;
+	/// assert!(!bucket.is_empty());
+	///
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	// Returns the up-to-date entry count, recomputing it with a full cursor walk
+	// the first time it's needed on a bucket whose on-disk meta predates `count`.
+	fn ensure_count(&self) -> u64 {
+		if self.meta.version >= META_VERSION {
+			return self.meta.count;
+		}
+		if let Some(count) = self.count_cache.get() {
+			return count;
+		}
+		let count = self.cursor().count() as u64;
+		self.count_cache.set(Some(count));
+		count
+	}
+
+	// Applies `delta` to the maintained entry counter, first recomputing it
+	// via `ensure_count` if it's still carrying over from a pre-`count` meta.
+	fn incr_count(&mut self, delta: i64) {
+		let count = self.ensure_count();
+		self.meta.count = (count as i64 + delta).max(0) as u64;
+		self.meta.version = META_VERSION;
+		self.count_cache.set(Some(self.meta.count));
+	}
+
 	/// Gets data from a bucket.
 	///
 	/// # Examples
@@ -348,6 +620,58 @@ impl Bucket {
 		Ok(())
 	}
 
+	/// Inserts a key / value pair only if the key doesn't already exist.
+	///
+	/// If the key is already present, the pre-existing value is returned untouched
+	/// (whether it's a key / value pair or a nested bucket) and nothing is written.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jammdb::{DB};
+	/// # use jammdb::Error;
+	///
+	/// # fn main() -> Result<(), Error> {
+	/// let mut db = DB::open("my.db")?;
+	/// let mut tx = db.tx(true)?;
+	///
+	/// let bucket = tx.create_bucket("my-bucket")?;
+	///
+	/// // inserted since the key doesn't exist yet
+	/// assert!(bucket.put_if_absent("123", "456")?.is_none());
+	///
+	/// // the existing value is returned, "456" is left untouched
+	/// let existing = bucket.put_if_absent("123", "789")?;
+	/// assert!(existing.is_some());
+	///
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn put_if_absent<T: AsRef<[u8]>, S: AsRef<[u8]>>(
+		&mut self,
+		key: T,
+		value: S,
+	) -> Result<Option<Data>> {
+		if !self.tx.writable {
+			return Err(Error::ReadOnlyTx);
+		}
+		let key = key.as_ref();
+		let mut c = self.cursor();
+		let exists = c.seek(key);
+		if exists {
+			return Ok(c.current());
+		}
+		let k = self.tx.copy_data(key);
+		let v = self.tx.copy_data(value.as_ref());
+		let data = Data::KeyValue(KVPair::from_slice_parts(k, v));
+		self.meta.next_int += 1;
+		self.incr_count(1);
+		let node = self.node(c.current_id());
+		node.insert_data(data);
+		self.dirty = true;
+		Ok(None)
+	}
+
 	/// Deletes a key-value pair from the bucket
 	pub fn delete<T: AsRef<[u8]>>(&mut self, key: T) -> Result<Data> {
 		let mut c = self.cursor();
@@ -356,6 +680,7 @@ impl Bucket {
 			let data = c.current().unwrap();
 			if data.is_kv() {
 				self.dirty = true;
+				self.incr_count(-1);
 				let node = self.node(c.current_id());
 				Ok(node.delete(c.current_index()))
 			} else {
@@ -376,6 +701,7 @@ impl Bucket {
 			}
 		} else {
 			self.meta.next_int += 1;
+			self.incr_count(1);
 		}
 		let node = self.node(c.current_id());
 		node.insert_data(data);
@@ -383,6 +709,164 @@ impl Bucket {
 		Ok(())
 	}
 
+	/// Bulk-loads already-sorted key / value pairs into the bucket.
+	///
+	/// `items` must yield keys in strictly ascending order; this is the caller's
+	/// responsibility, not checked by re-sorting, only by rejecting the first
+	/// out-of-order key with [`Error::KeysNotSorted`]. Because every key is known
+	/// to belong to the right of everything inserted so far, each one is appended
+	/// straight onto the bucket's rightmost leaf instead of paying for a fresh
+	/// root-to-leaf [`Cursor::seek`] per key, which makes loading a large presorted
+	/// dataset close to linear instead of O(n log n).
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jammdb::{DB};
+	/// # use jammdb::Error;
+	///
+	/// # fn main() -> Result<(), Error> {
+	/// let mut db = DB::open("my.db")?;
+	/// let mut tx = db.tx(true)?;
+	///
+	/// let bucket = tx.create_bucket("my-bucket")?;
+	///
+	/// bucket.put_sorted((0_u64..1_000).map(|i| (i.to_be_bytes(), i.to_be_bytes())))?;
+	///
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn put_sorted<I, K, V>(&mut self, items: I) -> Result<()>
+	where
+		I: IntoIterator<Item = (K, V)>,
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		if !self.tx.writable {
+			return Err(Error::ReadOnlyTx);
+		}
+		let mut leaf = self.rightmost_leaf();
+		// Seed with the bucket's current max key (if any) so the check below also
+		// rejects a batch that isn't strictly greater than what's already there,
+		// not just one that's internally out of order.
+		let mut prev_key: Option<Box<[u8]>> = {
+			let node = self.node(leaf);
+			match &node.data {
+				NodeData::Leaves(leaves) => leaves.last().map(|d| Box::from(d.key())),
+				NodeData::Branches(_) => unreachable!("rightmost_leaf always returns a leaf"),
+			}
+		};
+		if prev_key.is_none() {
+			// the rightmost leaf can be empty after deletes in this transaction
+			// while leaves to its left still hold keys
+			prev_key = self.cursor().last().map(|d| Box::from(d.key()));
+		}
+		for (key, value) in items {
+			let key = key.as_ref();
+			if let Some(prev) = prev_key.as_deref() {
+				if key <= prev {
+					return Err(Error::KeysNotSorted);
+				}
+			}
+			prev_key = Some(Box::from(key));
+
+			let k = self.tx.copy_data(key);
+			let v = self.tx.copy_data(value.as_ref());
+			// Count before mutating the leaf, like every other mutator (put_data,
+			// delete, put_if_absent): on a legacy-format bucket this is what's still
+			// missing the entry when `incr_count` falls back to a cursor walk.
+			self.meta.next_int += 1;
+			self.incr_count(1);
+			self.dirty = true;
+			{
+				let node = self.node(leaf);
+				match &mut node.data {
+					NodeData::Leaves(leaves) => {
+						leaves.push(Data::KeyValue(KVPair::from_slice_parts(k, v)));
+					}
+					NodeData::Branches(_) => unreachable!("rightmost_leaf always returns a leaf"),
+				}
+			}
+
+			leaf = self.split_leaf_if_overflowing(leaf);
+		}
+		Ok(())
+	}
+
+	// Walks down the rightmost branch at every level, recording each page's parent
+	// along the way (the same bookkeeping `node()` does when it loads a page lazily),
+	// so `split_leaf_if_overflowing` can later find its way back up to wire in siblings.
+	fn rightmost_leaf(&mut self) -> PageNodeID {
+		let mut id = self.root;
+		loop {
+			let (page_id, child) = {
+				let node = self.node(id);
+				let child = match &node.data {
+					NodeData::Leaves(_) => None,
+					NodeData::Branches(branches) => Some(branches.last().unwrap().page),
+				};
+				(node.page_id, child)
+			};
+			match child {
+				Some(child_page) => {
+					self.add_page_parent(child_page, page_id);
+					id = PageNodeID::Page(child_page);
+				}
+				None => return id,
+			}
+		}
+	}
+
+	// Checks only `leaf` itself for overflow; `Node::split` is a no-op (returns `None`)
+	// unless the node has actually grown past a page's worth of data. On a split the
+	// new siblings are wired into the parents by `propagate_split`. Returns the
+	// (possibly unchanged) id of the new rightmost leaf.
+	fn split_leaf_if_overflowing(&mut self, leaf: PageNodeID) -> PageNodeID {
+		let (page_id, new_branches) = {
+			let node = self.node(leaf);
+			(node.page_id, node.split())
+		};
+		match new_branches {
+			Some(branches) => {
+				let new_leaf = PageNodeID::Node(branches.last().unwrap().page);
+				self.propagate_split(page_id, branches);
+				new_leaf
+			}
+			None => leaf,
+		}
+	}
+
+	// Wires the new sibling(s) produced by a split into the parent branch node,
+	// recursing if that overflows the parent in turn, or promotes a brand new root
+	// the same way the end-of-transaction split loop in `rebalance` does.
+	fn propagate_split(&mut self, page_id: PageID, mut new_branches: Vec<Branch>) {
+		match self.page_parents.get(&page_id).copied() {
+			Some(parent_page) => {
+				for branch in &new_branches {
+					let key = self.node(PageNodeID::Node(branch.page)).data.key_parts();
+					self.add_page_parent(branch.page, parent_page);
+					let parent = self.node(PageNodeID::Page(parent_page));
+					parent.insert_child(branch.page, key);
+				}
+				let (parent_id, parent_split) = {
+					let parent = self.node(PageNodeID::Page(parent_page));
+					(parent.page_id, parent.split())
+				};
+				if let Some(parent_branches) = parent_split {
+					self.propagate_split(parent_id, parent_branches);
+				}
+			}
+			None => {
+				let node = self.node(PageNodeID::Node(page_id));
+				new_branches.insert(0, Branch::from_node(node));
+				let new_root = self.new_node(NodeData::Branches(new_branches));
+				let new_root_id = new_root.page_id;
+				self.root = PageNodeID::Node(new_root_id);
+				self.meta.root_page = new_root_id;
+			}
+		}
+	}
+
 	/// Get a cursor to iterate over the bucket.
 	///
 	///
@@ -412,6 +896,40 @@ impl Bucket {
 		Cursor::new(Ptr::new(self))
 	}
 
+	/// Returns a read-only, copy-on-write view of this bucket that lets you stage a
+	/// batch of [`put`](OverlayBucket::put)/[`delete`](OverlayBucket::delete) edits and
+	/// inspect the result with [`get`](OverlayBucket::get)/[`cursor`](OverlayBucket::cursor)
+	/// before deciding whether to apply them, without touching the bucket itself.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jammdb::{DB};
+	/// # use jammdb::Error;
+	///
+	/// # fn main() -> Result<(), Error> {
+	/// let mut db = DB::open("my.db")?;
+	/// let mut tx = db.tx(true)?;
+	///
+	/// let bucket = tx.create_bucket("my-bucket")?;
+	/// bucket.put("a", "1")?;
+	///
+	/// let mut overlay = bucket.overlay();
+	/// overlay.put("a", "2");
+	/// overlay.delete("does-not-exist");
+	///
+	/// // the overlay sees the staged change, the underlying bucket doesn't yet
+	/// assert!(overlay.get("a").is_some());
+	///
+	/// overlay.commit_into(bucket)?;
+	///
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn overlay(&self) -> OverlayBucket {
+		OverlayBucket::new(self)
+	}
+
 	pub(crate) fn page_node(&self, page: PageID) -> PageNode {
 		if let Some(node_id) = self.page_node_ids.get(&page) {
 			PageNode::Node(Ptr::new(self.nodes.get(*node_id).unwrap()))
@@ -461,7 +979,11 @@ impl Bucket {
 		}
 		for (k, b) in bucket_metas {
 			let name = self.tx.copy_data(&k[..]);
-			let meta = self.tx.copy_data(b.as_ref());
+			let bytes = b.to_bytes();
+			// `BucketData::meta()` decodes these bytes back with `BucketMeta::from_bytes`
+			// on the read path; check the roundtrip here, where the bytes are produced.
+			debug_assert_eq!(BucketMeta::from_bytes(&bytes), b);
+			let meta = self.tx.copy_data(&bytes[..]);
 			self.put_data(Data::Bucket(BucketData::from_slice_parts(name, meta)))?;
 		}
 		if self.dirty {
@@ -527,19 +1049,209 @@ impl Bucket {
 	}
 }
 
-const META_SIZE: usize = std::mem::size_of::<BucketMeta>();
+/// A read-only view that layers a set of pending edits over a committed [`Bucket`].
+///
+/// Created with [`Bucket::overlay`]. Nothing written to an `OverlayBucket` touches the
+/// underlying bucket until it's applied with [`commit_into`](#method.commit_into).
+pub struct OverlayBucket {
+	// A raw pointer rather than `&Bucket`, like `Cursor`'s backing pointer: an
+	// `OverlayBucket` is read-only over `base` right up until `commit_into`, at which
+	// point the caller needs `&mut` access to apply the edits - often to this same
+	// bucket - so this type must not hold a borrow-checker-visible borrow of it.
+	base: Ptr<Bucket>,
+	edits: HashMap<Vec<u8>, Option<Data>>,
+}
+
+impl OverlayBucket {
+	fn new(base: &Bucket) -> OverlayBucket {
+		OverlayBucket {
+			base: Ptr::new(base),
+			edits: HashMap::new(),
+		}
+	}
+
+	/// Stages a key / value pair, shadowing anything at that key in the base bucket
+	/// until this overlay is committed.
+	pub fn put<T: AsRef<[u8]>, S: AsRef<[u8]>>(&mut self, key: T, value: S) {
+		let key = key.as_ref();
+		let k = self.base.tx.copy_data(key);
+		let v = self.base.tx.copy_data(value.as_ref());
+		self.edits.insert(
+			Vec::from(key),
+			Some(Data::KeyValue(KVPair::from_slice_parts(k, v))),
+		);
+	}
+
+	/// Stages the removal of a key, shadowing it with a tombstone until this overlay
+	/// is committed, whether or not the key currently exists in the base bucket.
+	pub fn delete<T: AsRef<[u8]>>(&mut self, key: T) {
+		self.edits.insert(Vec::from(key.as_ref()), None);
+	}
+
+	/// Gets data from the overlay: a staged edit if there is one for this key,
+	/// otherwise whatever the base bucket has.
+	pub fn get<T: AsRef<[u8]>>(&self, key: T) -> Option<Data> {
+		let key = key.as_ref();
+		match self.edits.get(key) {
+			Some(edit) => edit.clone(),
+			None => self.base.get(key),
+		}
+	}
+
+	/// Returns a cursor over the union of the base bucket and the staged edits,
+	/// in key order, with tombstoned keys omitted.
+	pub fn cursor(&self) -> OverlayCursor {
+		let mut seen = std::collections::HashSet::new();
+		let mut from_base: Vec<(Vec<u8>, Data)> = Vec::new();
+		for data in self.base.cursor() {
+			let key = data.key().to_vec();
+			match self.edits.get(&key) {
+				Some(Some(replacement)) => from_base.push((key.clone(), replacement.clone())),
+				Some(None) => {}
+				None => from_base.push((key.clone(), data)),
+			}
+			seen.insert(key);
+		}
+		let mut inserted: Vec<(Vec<u8>, Data)> = self
+			.edits
+			.iter()
+			.filter(|(key, _)| !seen.contains(*key))
+			.filter_map(|(key, edit)| edit.clone().map(|data| (key.clone(), data)))
+			.collect();
+		inserted.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+		let mut from_base = from_base.into_iter().peekable();
+		let mut inserted = inserted.into_iter().peekable();
+		let mut merged = Vec::new();
+		loop {
+			let take_base = match (from_base.peek(), inserted.peek()) {
+				(Some((base_key, _)), Some((inserted_key, _))) => base_key <= inserted_key,
+				(Some(_), None) => true,
+				(None, Some(_)) => false,
+				(None, None) => break,
+			};
+			if take_base {
+				merged.push(from_base.next().unwrap().1);
+			} else {
+				merged.push(inserted.next().unwrap().1);
+			}
+		}
+		OverlayCursor {
+			items: merged.into_iter(),
+		}
+	}
+
+	/// Applies every staged edit to `target` and clears the overlay. The key a put
+	/// or delete was staged against is the only thing that matters; it doesn't have
+	/// to be the bucket this overlay was created from.
+	pub fn commit_into(self, target: &mut Bucket) -> Result<()> {
+		if !target.tx.writable {
+			return Err(Error::ReadOnlyTx);
+		}
+		// Sort so application order doesn't depend on `edits`' HashMap iteration order.
+		let mut edits: Vec<(Vec<u8>, Option<Data>)> = self.edits.into_iter().collect();
+		edits.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+		// Check every edit against what's already in `target` before applying any of
+		// them, so a bucket/kv type mismatch partway through can't leave `target`
+		// half-updated: either the whole overlay applies, or none of it does.
+		for (key, edit) in &edits {
+			if let Some(current) = target.get(key) {
+				let compatible = match edit {
+					Some(data) => current.is_kv() == data.is_kv(),
+					None => current.is_kv(),
+				};
+				if !compatible {
+					return Err(Error::IncompatibleValue);
+				}
+			}
+		}
+
+		for (key, edit) in edits {
+			match edit {
+				Some(data) => target.put_data(data)?,
+				None => match target.delete(key) {
+					Ok(_) | Err(Error::KeyValueMissing) => {}
+					Err(err) => return Err(err),
+				},
+			}
+		}
+		Ok(())
+	}
+}
+
+/// An iterator over the merged view produced by [`OverlayBucket::cursor`].
+pub struct OverlayCursor {
+	items: std::vec::IntoIter<Data>,
+}
+
+impl Iterator for OverlayCursor {
+	type Item = Data;
+
+	fn next(&mut self) -> Option<Data> {
+		self.items.next()
+	}
+}
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+// Wire size of the current format: root_page(8) + next_int(8) + count(8) + version(1).
+const META_SIZE: usize = 25;
+// Wire size before `count`/`version` existed: root_page(8) + next_int(8). A stored
+// meta this short, and no shorter or longer, predates this format change.
+const LEGACY_META_SIZE: usize = 16;
+
+// Bumped whenever the wire format in `BucketMeta::to_bytes`/`from_bytes` changes.
+// `from_bytes` tells a pre-`count` meta apart from a current one by its *length*
+// (`LEGACY_META_SIZE` vs. `META_SIZE`), not by reading an in-band tag that would
+// sit past the end of an old, shorter value. `Bucket::ensure_count` recomputes
+// `count` with a cursor walk the first time it sees `version < META_VERSION`.
+const META_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub(crate) struct BucketMeta {
 	pub(crate) root_page: PageID,
 	pub(crate) next_int: u64,
+	pub(crate) count: u64,
+	pub(crate) version: u8,
 }
 
-impl AsRef<[u8]> for BucketMeta {
-	#[inline]
-	fn as_ref(&self) -> &[u8] {
-		let ptr = self as *const BucketMeta as *const u8;
-		unsafe { std::slice::from_raw_parts(ptr, META_SIZE) }
+impl BucketMeta {
+	fn new() -> BucketMeta {
+		BucketMeta {
+			version: META_VERSION,
+			..Default::default()
+		}
+	}
+
+	// Explicit, length-prefixed-by-caller wire encoding, field by field. This is
+	// deliberately not a `#[repr(C)]` transmute of `Self`: that would serialize
+	// the struct's uninitialized inter-field padding (UB, and nondeterministic
+	// on-disk bytes), and it would silently change `META_SIZE` - and therefore
+	// the on-disk layout of every existing meta - the moment a field is added.
+	pub(crate) fn to_bytes(&self) -> [u8; META_SIZE] {
+		let mut buf = [0u8; META_SIZE];
+		buf[0..8].copy_from_slice(&(self.root_page as u64).to_le_bytes());
+		buf[8..16].copy_from_slice(&self.next_int.to_le_bytes());
+		buf[16..24].copy_from_slice(&self.count.to_le_bytes());
+		buf[24] = self.version;
+		buf
+	}
+
+	// Parses a meta written by any version of this format. `bytes` is expected to
+	// be either `LEGACY_META_SIZE` or `META_SIZE` long; a legacy-length meta has
+	// no `count`/`version` to read, so both are left at `0`, which is below
+	// `META_VERSION` and therefore recognized as stale.
+	pub(crate) fn from_bytes(bytes: &[u8]) -> BucketMeta {
+		debug_assert!(bytes.len() == LEGACY_META_SIZE || bytes.len() >= META_SIZE);
+		let mut meta = BucketMeta {
+			root_page: u64::from_le_bytes(bytes[0..8].try_into().unwrap()) as PageID,
+			next_int: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+			count: 0,
+			version: 0,
+		};
+		if bytes.len() >= META_SIZE {
+			meta.count = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+			meta.version = bytes[24];
+		}
+		meta
 	}
 }